@@ -1,18 +1,41 @@
+use std::{env, path::PathBuf, sync::Arc};
+
 use service::StorageService;
-use storage::storage_server::StorageServer;
+use storage::{file::FileStore, in_memory::InMemoryStore, s3::{S3Config, S3Store}, KeyStore};
 use tonic::transport::Server;
 
-pub mod storage {
+use messages::storage_server::StorageServer;
+
+pub mod messages {
     tonic::include_proto!("messages");
 }
 
 mod service;
+mod storage;
 
+/// Picks the `KeyStore` backend for this process.
+///
+/// `STORAGE_BACKEND` selects the backend: `memory` (the default), `file`
+/// (set `STORAGE_FILE_PATH` to override where its snapshot lives), or `s3`
+/// (configured via `S3_ENDPOINT`/`S3_REGION`/`S3_BUCKET`, see
+/// `storage::s3::S3Config`).
+async fn build_store() -> Arc<dyn KeyStore> {
+    match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("file") => {
+            let path = env::var("STORAGE_FILE_PATH")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("msg-store.json"));
+            Arc::new(FileStore::new(path))
+        }
+        Ok("s3") => Arc::new(S3Store::new(S3Config::from_env()).await),
+        _ => Arc::new(InMemoryStore::default()),
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let addr = "[::1]:8080".parse()?;
-    let storage_service = StorageService::default();
+    let storage_service = StorageService::new(build_store().await);
 
     Server::builder()
         .add_service(StorageServer::new(storage_service))
@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::service::{Key, TenantId};
+
+use super::{KeyRange, KeyStore};
+
+/// Bumped whenever `Snapshot`'s shape changes in a way older snapshots can't
+/// be read back as (e.g. chunk0-4 added the per-entry timestamp). A snapshot
+/// written by a different version fails loudly in `FileStore::new` instead
+/// of silently being treated as empty.
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    last_id: u64,
+    entries: HashMap<String, HashMap<String, (u64, i64)>>,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            last_id: 0,
+            entries: HashMap::new(),
+        }
+    }
+}
+
+/// Persistent backend: the tenant map is kept in memory and the whole
+/// snapshot is flushed to a single JSON file after every insert, so ids
+/// survive a restart. Good enough for a single instance that cannot afford
+/// to lose state but doesn't need to scale across processes.
+pub struct FileStore {
+    path: PathBuf,
+    last_id: AtomicU64,
+    entries: Arc<RwLock<HashMap<TenantId, RwLock<HashMap<Key, (u64, i64)>>>>>,
+}
+
+impl FileStore {
+    pub fn new(path: PathBuf) -> Self {
+        let snapshot = match std::fs::read_to_string(&path) {
+            Ok(raw) => {
+                let snapshot: Snapshot = serde_json::from_str(&raw)
+                    .unwrap_or_else(|e| panic!("{} is corrupt and cannot be read: {}", path.display(), e));
+                assert_eq!(
+                    snapshot.version, SNAPSHOT_VERSION,
+                    "{} was written by an incompatible version (snapshot version {}, expected {})",
+                    path.display(),
+                    snapshot.version,
+                    SNAPSHOT_VERSION,
+                );
+                snapshot
+            }
+            // No file yet is a legitimate fresh start. Anything else (permission
+            // denied, I/O error, ...) must not be papered over with an empty
+            // store, since that would silently discard whatever was there.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Snapshot::default(),
+            Err(e) => panic!("failed to read {}: {}", path.display(), e),
+        };
+
+        let mut entries = HashMap::new();
+        for (tenant, keys) in snapshot.entries {
+            let inner = keys.into_iter().map(|(key, entry)| (Key(key), entry)).collect();
+            entries.insert(TenantId(tenant), RwLock::new(inner));
+        }
+
+        Self {
+            path,
+            last_id: AtomicU64::new(snapshot.last_id.max(1)),
+            entries: Arc::new(RwLock::new(entries)),
+        }
+    }
+
+    async fn flush(&self) {
+        let mut snapshot = Snapshot {
+            version: SNAPSHOT_VERSION,
+            last_id: self.last_id.load(Ordering::SeqCst),
+            entries: HashMap::new(),
+        };
+        for (tenant, keys) in self.entries.read().await.iter() {
+            let keys = keys
+                .read()
+                .await
+                .iter()
+                .map(|(key, entry)| (key.0.clone(), *entry))
+                .collect();
+            snapshot.entries.insert(tenant.0.clone(), keys);
+        }
+
+        // Write to a sibling temp file and rename it into place, so a crash
+        // mid-write can never leave `self.path` holding a half-written (and
+        // therefore corrupt-looking) snapshot.
+        let raw = serde_json::to_string(&snapshot).expect("snapshot is not serializable");
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        if std::fs::write(&tmp_path, raw).is_ok() {
+            let _ = std::fs::rename(&tmp_path, &self.path);
+        }
+    }
+}
+
+#[async_trait]
+impl KeyStore for FileStore {
+    async fn get_next_id(&self) -> u64 {
+        self.last_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    async fn insert(&self, tenant_id: TenantId, key: Key) -> (bool, u64, i64) {
+        // Case 1. if the tenant map does not exist create it and add the key
+        if !self.entries.read().await.contains_key(&tenant_id) {
+            let mut tenant_map = HashMap::new();
+            let id = self.get_next_id().await;
+            let timestamp = Utc::now().timestamp();
+            tenant_map.insert(key, (id, timestamp));
+            self.entries
+                .write()
+                .await
+                .insert(tenant_id.clone(), RwLock::new(tenant_map));
+            self.flush().await;
+            return (true, id, timestamp);
+        }
+
+        let maybe_value = self
+            .entries
+            .read()
+            .await
+            .get(&tenant_id)
+            .unwrap()
+            .read()
+            .await
+            .get(&key)
+            .cloned();
+
+        match maybe_value {
+            // Case 2. if the key does not exist add it
+            None => {
+                let id = self.get_next_id().await;
+                let timestamp = Utc::now().timestamp();
+                self.entries
+                    .write()
+                    .await
+                    .get(&tenant_id)
+                    .unwrap()
+                    .write()
+                    .await
+                    .insert(key, (id, timestamp));
+                self.flush().await;
+                (true, id, timestamp)
+            }
+
+            // Case 3. if the key exists return the value
+            Some((id, timestamp)) => (false, id, timestamp),
+        }
+    }
+
+    async fn scan(&self, tenant_id: TenantId, range: KeyRange) -> Vec<(Key, u64, i64)> {
+        let entries = self.entries.read().await;
+        let Some(tenant_map) = entries.get(&tenant_id) else {
+            return Vec::new();
+        };
+        let tenant_map = tenant_map.read().await;
+
+        let mut matches: Vec<(Key, u64, i64)> = tenant_map
+            .iter()
+            .filter(|(key, _)| {
+                range.begin.as_ref().map_or(true, |begin| key.0 >= *begin)
+                    && range.end.as_ref().map_or(true, |end| key.0 <= *end)
+            })
+            .map(|(key, (id, timestamp))| (key.clone(), *id, *timestamp))
+            .collect();
+
+        matches.sort_by(|a, b| a.0.0.cmp(&b.0.0));
+
+        if let Some(limit) = range.limit {
+            matches.truncate(limit);
+        }
+
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::service::{Key, TenantId};
+    use crate::storage::KeyStore;
+
+    use super::FileStore;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("msg-store-test-{}-{}.json", name, std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn test_file_store_survives_restart() {
+        let path = temp_path("survives-restart");
+        let _ = std::fs::remove_file(&path);
+
+        let tenant = TenantId::new("3bd1c697".into()).unwrap();
+        let key = Key::new("K-h53dk-A".into()).unwrap();
+
+        let (new, id, timestamp) = FileStore::new(path.clone())
+            .insert(tenant.clone(), key.clone())
+            .await;
+        assert!(new);
+
+        // Reconstruct from the same path, simulating a process restart.
+        let restarted = FileStore::new(path.clone());
+
+        let (new_again, id_again, timestamp_again) =
+            restarted.insert(tenant.clone(), key).await;
+        assert_eq!((new_again, id_again, timestamp_again), (false, id, timestamp));
+
+        // A new key after the restart should get an id the old process
+        // never handed out, not a reused/lower one.
+        let (new, next_id, _) = restarted
+            .insert(tenant, Key::new("K-867vc-C".into()).unwrap())
+            .await;
+        assert!(new);
+        assert!(next_id > id);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "is corrupt")]
+    async fn test_file_store_panics_on_corrupt_snapshot() {
+        let path = temp_path("corrupt-snapshot");
+        std::fs::write(&path, "not json").unwrap();
+
+        FileStore::new(path.clone());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
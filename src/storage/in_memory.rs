@@ -0,0 +1,287 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::sync::RwLock;
+
+use crate::service::{Key, TenantId};
+
+use super::{KeyRange, KeyStore};
+
+pub type TenantMap = HashMap<TenantId, RwLock<HashMap<Key, (u64, i64)>>>;
+
+/// Default backend: everything lives in memory and is lost on restart.
+pub struct InMemoryStore {
+    last_id: AtomicU64,
+    entries: Arc<RwLock<TenantMap>>,
+}
+
+impl Default for InMemoryStore {
+    fn default() -> Self {
+        Self {
+            last_id: AtomicU64::new(1),
+            entries: Arc::new(RwLock::new(TenantMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl KeyStore for InMemoryStore {
+    async fn get_next_id(&self) -> u64 {
+        self.last_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    async fn insert(&self, tenant_id: TenantId, key: Key) -> (bool, u64, i64) {
+        // Case 1. if the tenant map does not exist create it and add the key
+        if !self.entries.read().await.contains_key(&tenant_id) {
+            let mut tenant_map = HashMap::new();
+            let id = self.get_next_id().await;
+            let timestamp = Utc::now().timestamp();
+            tenant_map.insert(key, (id, timestamp));
+            self.entries
+                .write()
+                .await
+                .insert(tenant_id.clone(), RwLock::new(tenant_map));
+            return (true, id, timestamp);
+        }
+
+        let maybe_value = self
+            .entries
+            .read()
+            .await
+            .get(&tenant_id)
+            .unwrap()
+            .read()
+            .await
+            .get(&key)
+            .cloned();
+
+        match maybe_value {
+            // Case 2. if the key does not exist add it
+            None => {
+                let id = self.get_next_id().await;
+                let timestamp = Utc::now().timestamp();
+                self.entries
+                    .write()
+                    .await
+                    .get(&tenant_id)
+                    .unwrap()
+                    .write()
+                    .await
+                    .insert(key, (id, timestamp));
+                (true, id, timestamp)
+            }
+
+            // Case 3. if the key exists return the value
+            Some((id, timestamp)) => (false, id, timestamp),
+        }
+    }
+
+    async fn insert_batch(&self, tenant_id: TenantId, keys: Vec<Key>) -> Vec<(bool, u64, i64)> {
+        // Make sure the tenant partition exists before taking its lock for
+        // the whole batch, same as a single insert would.
+        if !self.entries.read().await.contains_key(&tenant_id) {
+            self.entries
+                .write()
+                .await
+                .entry(tenant_id.clone())
+                .or_insert_with(|| RwLock::new(HashMap::new()));
+        }
+
+        let entries = self.entries.read().await;
+        let mut tenant_map = entries.get(&tenant_id).unwrap().write().await;
+
+        // Allocate all the ids this batch needs in one fetch_add instead of
+        // one per new key. Dedupe against keys already present *and* against
+        // each other first, so a repeated not-yet-present key in the same
+        // batch doesn't reserve (and waste) two ids for a single insertion.
+        let mut to_create = HashSet::new();
+        for key in &keys {
+            if !tenant_map.contains_key(key) {
+                to_create.insert(key);
+            }
+        }
+        let mut next_id = self.last_id.fetch_add(to_create.len() as u64, Ordering::SeqCst);
+
+        keys.into_iter()
+            .map(|key| match tenant_map.get(&key) {
+                Some((id, timestamp)) => (false, *id, *timestamp),
+                None => {
+                    let id = next_id;
+                    next_id += 1;
+                    let timestamp = Utc::now().timestamp();
+                    tenant_map.insert(key, (id, timestamp));
+                    (true, id, timestamp)
+                }
+            })
+            .collect()
+    }
+
+    async fn scan(&self, tenant_id: TenantId, range: KeyRange) -> Vec<(Key, u64, i64)> {
+        let entries = self.entries.read().await;
+        let Some(tenant_map) = entries.get(&tenant_id) else {
+            return Vec::new();
+        };
+        let tenant_map = tenant_map.read().await;
+
+        let mut matches: Vec<(Key, u64, i64)> = tenant_map
+            .iter()
+            .filter(|(key, _)| {
+                range.begin.as_ref().map_or(true, |begin| key.0 >= *begin)
+                    && range.end.as_ref().map_or(true, |end| key.0 <= *end)
+            })
+            .map(|(key, (id, timestamp))| (key.clone(), *id, *timestamp))
+            .collect();
+
+        matches.sort_by(|a, b| a.0.0.cmp(&b.0.0));
+
+        if let Some(limit) = range.limit {
+            matches.truncate(limit);
+        }
+
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::{Key, TenantId};
+    use crate::storage::KeyStore;
+
+    use super::InMemoryStore;
+
+    #[tokio::test]
+    async fn test_in_memory_store_sample_data() {
+        let under_test = InMemoryStore::default();
+        let msg1 = (
+            TenantId::new("3bd1c697".into()).unwrap(),
+            Key::new("K-h53dk-A".into()).unwrap(),
+        );
+        let msg2 = (
+            TenantId::new("75682017".into()).unwrap(),
+            Key::new("K-h53dk-A".into()).unwrap(),
+        );
+        let msg3 = (
+            TenantId::new("3bd1c697".into()).unwrap(),
+            Key::new("K-867vc-C".into()).unwrap(),
+        );
+        let msg4 = (
+            TenantId::new("75682017".into()).unwrap(),
+            Key::new("K-h53dk-A".into()).unwrap(),
+        );
+
+        let resp1 = under_test.insert(msg1.0, msg1.1).await;
+        let resp2 = under_test.insert(msg2.0, msg2.1).await;
+        let resp3 = under_test.insert(msg3.0, msg3.1).await;
+        let resp4 = under_test.insert(msg4.0, msg4.1).await;
+
+        assert_eq!((resp1.0, resp1.1), (true, 1));
+        assert_eq!((resp2.0, resp2.1), (true, 2));
+        assert_eq!((resp3.0, resp3.1), (true, 3));
+        assert_eq!((resp4.0, resp4.1), (false, 2));
+        assert_eq!(resp4.2, resp2.2);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_insert_batch() {
+        let under_test = InMemoryStore::default();
+        let tenant = TenantId::new("3bd1c697".into()).unwrap();
+
+        under_test
+            .insert(tenant.clone(), Key::new("K-h53dk-A".into()).unwrap())
+            .await;
+
+        let results = under_test
+            .insert_batch(
+                tenant,
+                vec![
+                    Key::new("K-h53dk-A".into()).unwrap(),
+                    Key::new("K-867vc-C".into()).unwrap(),
+                    Key::new("K-111vc-B".into()).unwrap(),
+                ],
+            )
+            .await
+            .into_iter()
+            .map(|(new, id, _)| (new, id))
+            .collect::<Vec<_>>();
+
+        assert_eq!(results, vec![(false, 1), (true, 2), (true, 3)]);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_insert_batch_duplicate_key_does_not_burn_an_id() {
+        let under_test = InMemoryStore::default();
+        let tenant = TenantId::new("3bd1c697".into()).unwrap();
+
+        let results = under_test
+            .insert_batch(
+                tenant.clone(),
+                vec![
+                    Key::new("K-h53dk-A".into()).unwrap(),
+                    Key::new("K-h53dk-A".into()).unwrap(),
+                ],
+            )
+            .await
+            .into_iter()
+            .map(|(new, id, _)| (new, id))
+            .collect::<Vec<_>>();
+
+        assert_eq!(results, vec![(true, 1), (false, 1)]);
+
+        // The next key inserted anywhere for this tenant should get id 2,
+        // not 3 — no id should have been reserved and discarded.
+        let (new, id, _) = under_test
+            .insert(tenant, Key::new("K-867vc-C".into()).unwrap())
+            .await;
+        assert_eq!((new, id), (true, 2));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_scan() {
+        use crate::storage::KeyRange;
+
+        let under_test = InMemoryStore::default();
+        let tenant = TenantId::new("3bd1c697".into()).unwrap();
+
+        under_test
+            .insert(tenant.clone(), Key::new("K-h53dk-A".into()).unwrap())
+            .await;
+        under_test
+            .insert(tenant.clone(), Key::new("K-867vc-C".into()).unwrap())
+            .await;
+        under_test
+            .insert(tenant.clone(), Key::new("K-111vc-B".into()).unwrap())
+            .await;
+
+        let all = under_test.scan(tenant.clone(), KeyRange::default()).await;
+        assert_eq!(
+            all.into_iter().map(|(key, id, _)| (key, id)).collect::<Vec<_>>(),
+            vec![
+                (Key::new("K-111vc-B".into()).unwrap(), 3),
+                (Key::new("K-867vc-C".into()).unwrap(), 2),
+                (Key::new("K-h53dk-A".into()).unwrap(), 1),
+            ]
+        );
+
+        let bounded = under_test
+            .scan(
+                tenant,
+                KeyRange {
+                    begin: Some("K-2".into()),
+                    end: None,
+                    limit: Some(1),
+                },
+            )
+            .await;
+        assert_eq!(
+            bounded.into_iter().map(|(key, id, _)| (key, id)).collect::<Vec<_>>(),
+            vec![(Key::new("K-867vc-C".into()).unwrap(), 2)]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+
+use crate::service::{Key, TenantId};
+
+pub mod file;
+pub mod in_memory;
+pub mod s3;
+
+/// Bounds for `KeyStore::scan`. `begin`/`end` are inclusive and compare
+/// against the validated key string; `limit` caps how many entries come
+/// back. Any of them can be left unset to mean "unbounded".
+#[derive(Debug, Clone, Default)]
+pub struct KeyRange {
+    pub begin: Option<String>,
+    pub end: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Pluggable persistence layer for the tenant -> key -> id mapping.
+///
+/// An implementation only needs to guarantee that `insert` is idempotent for
+/// a given `(tenant, key)` pair and that ids keep increasing, so `main.rs`
+/// can swap backends (in-memory, file-backed, ...) without touching
+/// `StorageService` or the gRPC layer.
+#[async_trait]
+pub trait KeyStore: Send + Sync {
+    async fn get_next_id(&self) -> u64;
+
+    /// Inserts `key` for `tenant`, returning whether the id is newly
+    /// assigned along with the id and the timestamp it was first assigned
+    /// at (not the current time, so callers can tell when a key was
+    /// actually created).
+    async fn insert(&self, tenant: TenantId, key: Key) -> (bool, u64, i64);
+
+    /// Inserts many keys for the same tenant at once. The default
+    /// implementation just calls `insert` in a loop; backends for which
+    /// that is wasteful (e.g. taking a lock per key) should override it.
+    async fn insert_batch(&self, tenant: TenantId, keys: Vec<Key>) -> Vec<(bool, u64, i64)> {
+        let mut results = Vec::with_capacity(keys.len());
+        for key in keys {
+            results.push(self.insert(tenant.clone(), key).await);
+        }
+        results
+    }
+
+    /// Lists the `(key, id, timestamp)` entries of a tenant whose key falls
+    /// within `range`, sorted by key.
+    async fn scan(&self, tenant: TenantId, range: KeyRange) -> Vec<(Key, u64, i64)>;
+}
@@ -0,0 +1,238 @@
+use async_trait::async_trait;
+use aws_sdk_s3::{primitives::ByteStream, Client};
+use chrono::Utc;
+
+use crate::service::{Key, TenantId};
+
+use super::{KeyRange, KeyStore};
+
+const COUNTER_KEY: &str = "__last_id";
+
+/// Connection settings for an S3-compatible object store (AWS S3, MinIO,
+/// Garage, ...). Credentials are left to the AWS SDK's default provider
+/// chain (env vars, shared profile, instance role).
+pub struct S3Config {
+    pub endpoint: Option<String>,
+    pub region: String,
+    pub bucket: String,
+}
+
+impl S3Config {
+    /// Reads `S3_ENDPOINT` (optional, set it for MinIO/Garage), `S3_REGION`
+    /// and `S3_BUCKET` from the environment.
+    pub fn from_env() -> Self {
+        Self {
+            endpoint: std::env::var("S3_ENDPOINT").ok(),
+            region: std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".into()),
+            bucket: std::env::var("S3_BUCKET").expect("S3_BUCKET must be set"),
+        }
+    }
+}
+
+/// Persistent backend storing each tenant as a partition (key prefix) and
+/// each key's assigned `id:timestamp` as a small object underneath it, so
+/// mappings survive a restart. Works against any S3-compatible store,
+/// including MinIO and Garage. The id counter is updated with an ETag CAS
+/// (see `allocate_id`), so multiple `S3Store` instances can safely share one
+/// bucket.
+pub struct S3Store {
+    client: Client,
+    bucket: String,
+}
+
+impl S3Store {
+    pub async fn new(config: S3Config) -> Self {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new(config.region));
+        if let Some(endpoint) = config.endpoint.clone() {
+            loader = loader.endpoint_url(endpoint);
+        }
+        let client = Client::new(&loader.load().await);
+
+        Self {
+            client,
+            bucket: config.bucket,
+        }
+    }
+
+    fn object_key(tenant: &TenantId, key: &Key) -> String {
+        format!("{}/{}", tenant.0, key.0)
+    }
+
+    fn encode_entry(id: u64, timestamp: i64) -> String {
+        format!("{}:{}", id, timestamp)
+    }
+
+    fn decode_entry(raw: &str) -> Option<(u64, i64)> {
+        let (id, timestamp) = raw.split_once(':')?;
+        Some((id.parse().ok()?, timestamp.parse().ok()?))
+    }
+
+    async fn read_entry(client: &Client, bucket: &str, object_key: &str) -> Option<(u64, i64)> {
+        let output = client
+            .get_object()
+            .bucket(bucket)
+            .key(object_key)
+            .send()
+            .await
+            .ok()?;
+        let bytes = output.body.collect().await.ok()?.into_bytes();
+        Self::decode_entry(std::str::from_utf8(&bytes).ok()?)
+    }
+
+    /// Reads the persisted "next id" counter along with its ETag, so the
+    /// caller can CAS it forward. A missing object means nothing has been
+    /// allocated yet (next id is 1); any other error is a genuine failure
+    /// and must not be treated as "start over from 1", or a transient blip
+    /// would silently reset every id ever handed out.
+    async fn read_counter(&self) -> (u64, Option<String>) {
+        match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(COUNTER_KEY)
+            .send()
+            .await
+        {
+            Ok(output) => {
+                let etag = output.e_tag().map(str::to_owned);
+                let bytes = output
+                    .body
+                    .collect()
+                    .await
+                    .unwrap_or_else(|e| panic!("failed to read {} body: {}", COUNTER_KEY, e))
+                    .into_bytes();
+                let value = std::str::from_utf8(&bytes)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or_else(|| panic!("corrupt {} counter value", COUNTER_KEY));
+                (value, etag)
+            }
+            Err(err) if err.as_service_error().is_some_and(|e| e.is_no_such_key()) => (1, None),
+            Err(err) => panic!("failed to read {} counter: {}", COUNTER_KEY, err),
+        }
+    }
+
+    /// Atomically bumps the persisted counter and returns the id it held
+    /// before the bump. The put is conditioned on the ETag just read (or on
+    /// the object not existing yet), so if another replica updates the
+    /// counter first this retries against the fresh value instead of
+    /// clobbering it — this is what keeps id allocation correct when
+    /// several `S3Store` instances share a bucket.
+    async fn allocate_id(&self) -> u64 {
+        loop {
+            let (next_id, etag) = self.read_counter().await;
+
+            let mut put = self
+                .client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(COUNTER_KEY)
+                .body(ByteStream::from((next_id + 1).to_string().into_bytes()));
+            put = match &etag {
+                Some(etag) => put.if_match(etag),
+                None => put.if_none_match("*"),
+            };
+
+            if put.send().await.is_ok() {
+                return next_id;
+            }
+            // Someone else updated the counter first; retry with fresh state.
+        }
+    }
+}
+
+#[async_trait]
+impl KeyStore for S3Store {
+    async fn get_next_id(&self) -> u64 {
+        self.allocate_id().await
+    }
+
+    async fn insert(&self, tenant_id: TenantId, key: Key) -> (bool, u64, i64) {
+        let object_key = Self::object_key(&tenant_id, &key);
+
+        // Case 3. if the key already exists return its id
+        if let Some((id, timestamp)) = Self::read_entry(&self.client, &self.bucket, &object_key).await {
+            return (false, id, timestamp);
+        }
+
+        let id = self.get_next_id().await;
+        let timestamp = Utc::now().timestamp();
+
+        // Case 1/2. conditional put: only succeeds if nobody raced us to
+        // create this object first, which is what keeps "first writer wins"
+        // true across restarts and across replicas of this service.
+        let put = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&object_key)
+            .if_none_match("*")
+            .body(ByteStream::from(
+                Self::encode_entry(id, timestamp).into_bytes(),
+            ))
+            .send()
+            .await;
+
+        match put {
+            Ok(_) => (true, id, timestamp),
+            Err(_) => {
+                // Someone else won the race; read back what they wrote.
+                let (existing_id, existing_timestamp) =
+                    Self::read_entry(&self.client, &self.bucket, &object_key)
+                        .await
+                        .unwrap_or((id, timestamp));
+                (false, existing_id, existing_timestamp)
+            }
+        }
+    }
+
+    async fn scan(&self, tenant_id: TenantId, range: KeyRange) -> Vec<(Key, u64, i64)> {
+        let prefix = format!("{}/", tenant_id.0);
+
+        let mut object_keys = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let mut request = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&prefix);
+            if let Some(token) = continuation_token.take() {
+                request = request.continuation_token(token);
+            }
+            let Ok(output) = request.send().await else {
+                break;
+            };
+            object_keys.extend(output.contents().iter().filter_map(|o| o.key().map(str::to_owned)));
+
+            if output.is_truncated().unwrap_or(false) {
+                continuation_token = output.next_continuation_token().map(str::to_owned);
+            } else {
+                break;
+            }
+        }
+
+        let mut matches = Vec::new();
+        for object_key in object_keys {
+            let key = object_key.trim_start_matches(&prefix).to_string();
+            if range.begin.as_ref().is_some_and(|begin| key < *begin) {
+                continue;
+            }
+            if range.end.as_ref().is_some_and(|end| key > *end) {
+                continue;
+            }
+            if let Some((id, timestamp)) = Self::read_entry(&self.client, &self.bucket, &object_key).await {
+                matches.push((Key(key), id, timestamp));
+            }
+        }
+
+        matches.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+
+        if let Some(limit) = range.limit {
+            matches.truncate(limit);
+        }
+
+        matches
+    }
+}